@@ -1,5 +1,8 @@
-use std::ffi::CStr;
+use std::collections::BTreeMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
 use aws_s3_presign::GetSignedUrlOptions;
+use chrono::{TimeZone, Utc};
 
 #[no_mangle]
 pub extern "C" fn add(a: f64, b:f64) -> f64
@@ -7,33 +10,131 @@ pub extern "C" fn add(a: f64, b:f64) -> f64
    return a + b;
 }
 
+#[repr(C)]
+pub struct FfiGetSignedUrlOptions {
+    pub key: *const c_char,
+    pub method: *const c_char,
+    pub region: *const c_char,
+    pub expires_in: i32,
+    pub date_unix_timestamp: i64,
+    pub bucket: *const c_char,
+    pub access_key_id: *const c_char,
+    pub secret_access_key: *const c_char,
+    pub endpoint: *const c_char,
+    pub session_token: *const c_char,
+    pub signed_headers: *const c_char,
+    pub pre_signature: *const u8,
+    pub pre_signature_len: usize,
+}
+
+unsafe fn c_str_to_string(ptr: *const c_char) -> String
+{
+    if ptr.is_null() {
+        return String::new();
+    }
+
+    return CStr::from_ptr(ptr).to_string_lossy().into_owned();
+}
+
+unsafe fn c_str_to_optional_string(ptr: *const c_char) -> Option<String>
+{
+    if ptr.is_null() {
+        return None;
+    }
+
+    return Some(CStr::from_ptr(ptr).to_string_lossy().into_owned());
+}
+
+unsafe fn c_str_to_signed_headers(ptr: *const c_char) -> BTreeMap<String, String>
+{
+    let mut headers: BTreeMap<String, String> = BTreeMap::new();
+
+    if ptr.is_null() {
+        return headers;
+    }
+
+    for pair in c_str_to_string(ptr).split(';') {
+        if let Some((name, value)) = pair.split_once(':') {
+            headers.insert(name.to_string(), value.to_string());
+        }
+    }
+
+    return headers;
+}
+
+unsafe fn c_pre_signature(ptr: *const u8, len: usize) -> Option<Vec<u8>>
+{
+    if ptr.is_null() || len == 0 {
+        return None;
+    }
+
+    return Some(std::slice::from_raw_parts(ptr, len).to_vec());
+}
+
+unsafe fn ffi_options_to_options(ffi_options: *const FfiGetSignedUrlOptions) -> GetSignedUrlOptions
+{
+    let ffi_options = &*ffi_options;
+    let date = Utc.timestamp_opt(ffi_options.date_unix_timestamp, 0).single().unwrap_or_else(Utc::now);
+
+    return GetSignedUrlOptions {
+        key: c_str_to_string(ffi_options.key),
+        method: c_str_to_string(ffi_options.method),
+        region: c_str_to_string(ffi_options.region),
+        expires_in: ffi_options.expires_in,
+        date,
+        bucket: c_str_to_string(ffi_options.bucket),
+        access_key_id: c_str_to_string(ffi_options.access_key_id),
+        secret_access_key: c_str_to_string(ffi_options.secret_access_key),
+        endpoint: c_str_to_string(ffi_options.endpoint),
+        session_token: c_str_to_optional_string(ffi_options.session_token),
+        signed_headers: c_str_to_signed_headers(ffi_options.signed_headers),
+        pre_signature: c_pre_signature(ffi_options.pre_signature, ffi_options.pre_signature_len),
+    };
+}
+
+fn string_to_owned_c_char(value: String) -> *mut c_char
+{
+    return CString::new(value).unwrap_or_default().into_raw();
+}
+
 #[no_mangle]
-pub extern "C" fn ffi_get_signature_key(secret_access_key_ptr: *const i8) -> *const u8
+pub extern "C" fn ffi_get_signature_key(secret_access_key_ptr: *const c_char) -> *mut c_char
 {
     unsafe
     {
-        let secret_access_key = CStr::from_ptr(secret_access_key_ptr).to_str().unwrap();
-        println!("Rust String Received: {}", &secret_access_key);
-
-        let options: GetSignedUrlOptions = GetSignedUrlOptions {
-            secret_access_key: String::from(secret_access_key),
+        let options = GetSignedUrlOptions {
+            secret_access_key: c_str_to_string(secret_access_key_ptr),
             ..GetSignedUrlOptions::default()
         };
 
-        let signature_vec = aws_s3_presign::get_signature_key(&options);
-        println!("Rust Signature: {:?}", signature_vec);
-        return signature_vec.as_ptr();
+        let signature_key = aws_s3_presign::get_signature_key(&options);
+        let hex_key: String = signature_key.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        return string_to_owned_c_char(hex_key);
     }
 }
 
 #[no_mangle]
-pub extern "C" fn ffi_get_signed_url(_options: *const i8, signature_key_ptr: *const i8) ->  *const u8
+pub extern "C" fn ffi_get_signed_url(options: *const FfiGetSignedUrlOptions) -> *mut c_char
 {
     unsafe
     {
-        let signature_key = signature_key_ptr.as_ref().unwrap();
-        println!("Rust Signature: {:?}", signature_key);
+        let options = ffi_options_to_options(options);
+        let signed_url = aws_s3_presign::get_signed_url(&options);
+
+        return string_to_owned_c_char(signed_url);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn ffi_free_string(ptr: *mut c_char)
+{
+    if ptr.is_null() {
+        return;
+    }
 
-        return "qwerty\0".as_bytes().as_ptr();
+    unsafe
+    {
+        drop(CString::from_raw(ptr));
     }
 }
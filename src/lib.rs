@@ -1,10 +1,9 @@
 use hmac::{Hmac, Mac};
 use sha2::{Sha256, Digest};
-use std::collections::HashMap;
-use chrono::{DateTime, Utc};
-use url_search_params::build_url_search_params;
+use std::collections::BTreeMap;
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GetSignedUrlOptions {
     pub key: String,
     pub method: String,
@@ -15,7 +14,9 @@ pub struct GetSignedUrlOptions {
     pub access_key_id: String,
     pub secret_access_key: String,
     pub endpoint: String,
-    pub pre_signature: Option<Vec<u8>>
+    pub pre_signature: Option<Vec<u8>>,
+    pub signed_headers: BTreeMap<String, String>,
+    pub session_token: Option<String>,
 }
 
 impl Default for GetSignedUrlOptions {
@@ -31,6 +32,8 @@ impl Default for GetSignedUrlOptions {
             secret_access_key: String::from("key_secret"),
             endpoint: String::from("endpoint"),
             pre_signature: None,
+            signed_headers: BTreeMap::new(),
+            session_token: None,
         }
     }
 }
@@ -41,6 +44,12 @@ fn sha256(data: &String) -> String {
     return format!("{:x}",hasher.finalize());
 }
 
+fn sha256_bytes(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    return format!("{:x}", hasher.finalize());
+}
+
 fn hmac_sha_256(key: &Vec<u8>, data: &Vec<u8>) -> Vec<u8> {
     let mut hasher = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take key of any size");
     hasher.update(data);
@@ -53,26 +62,94 @@ fn hmac_sha_256_hex(key: &Vec<u8>, data: &String) -> String {
     return format!("{:x}", hasher.finalize().into_bytes());
 }
 
-fn get_query_parameters(options: &GetSignedUrlOptions) -> String
+fn get_canonical_signed_headers(options: &GetSignedUrlOptions) -> BTreeMap<String, String>
 {
-    let mut url_params: HashMap<String, String>= HashMap::new();
-    url_params.insert("X-Amz-Algorithm".to_string(), "AWS4-HMAC-SHA256".to_string());
-    url_params.insert("X-Amz-Credential".to_string(), options.access_key_id.to_string() + "/" + &options.date.format("%Y%m%d").to_string() + "/" + &options.region + "/s3/aws4_request");
-    url_params.insert("X-Amz-Date".to_string(), options.date.format("%Y%m%dT%H%M%SZ").to_string());
-    url_params.insert("X-Amz-Expires".to_string(), options.expires_in.to_string());
-    url_params.insert("X-Amz-SignedHeaders".to_string(), "host".to_string());
-    return build_url_search_params(url_params);
+    let mut headers: BTreeMap<String, String> = BTreeMap::new();
+
+    for (name, value) in &options.signed_headers {
+        let name = name.to_lowercase();
+        if name == "host" {
+            continue;
+        }
+
+        headers.insert(name, value.trim().to_string());
+    }
+
+    headers.insert("host".to_string(), options.bucket.clone() + "." + &options.endpoint);
+
+    return headers;
 }
 
-fn get_canonical_request(options: &GetSignedUrlOptions, query_parameters: &String) -> String
+fn get_signed_headers_names(headers: &BTreeMap<String, String>) -> String
 {
-    let key = &("/".to_string() + &options.key);
-    let host = &("host:".to_string() + &options.bucket + "." + &options.endpoint);
+    return headers.keys().cloned().collect::<Vec<String>>().join(";");
+}
 
-    let canonical_request: Vec<&str> = vec![&options.method, key, query_parameters, host, "", "host", "UNSIGNED-PAYLOAD"];
+fn uri_encode(input: &str, preserve_slash: bool) -> String
+{
+    let mut out = String::with_capacity(input.len());
+
+    for byte in input.bytes() {
+        let c = byte as char;
+        let is_unreserved = c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~');
+
+        if is_unreserved || (preserve_slash && c == '/') {
+            out.push(c);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    return out;
+}
+
+fn uri_encode_path(key: &str) -> String
+{
+    return "/".to_string() + &uri_encode(key, true);
+}
+
+fn build_canonical_query_string(params: &BTreeMap<String, String>) -> String
+{
+    return params.iter()
+        .map(|(key, value)| format!("{}={}", uri_encode(key, false), uri_encode(value, false)))
+        .collect::<Vec<String>>()
+        .join("&");
+}
+
+fn get_canonical_query_string(options: &GetSignedUrlOptions) -> String
+{
+    let mut params: BTreeMap<String, String> = BTreeMap::new();
+    params.insert("X-Amz-Algorithm".to_string(), "AWS4-HMAC-SHA256".to_string());
+    params.insert("X-Amz-Credential".to_string(), options.access_key_id.to_string() + "/" + &options.date.format("%Y%m%d").to_string() + "/" + &options.region + "/s3/aws4_request");
+    params.insert("X-Amz-Date".to_string(), options.date.format("%Y%m%dT%H%M%SZ").to_string());
+    params.insert("X-Amz-Expires".to_string(), options.expires_in.to_string());
+    params.insert("X-Amz-SignedHeaders".to_string(), get_signed_headers_names(&get_canonical_signed_headers(options)));
+
+    if let Some(session_token) = &options.session_token {
+        params.insert("X-Amz-Security-Token".to_string(), session_token.clone());
+    }
+
+    return build_canonical_query_string(&params);
+}
+
+fn build_canonical_request(method: &str, key: &str, query_parameters: &str, headers: &BTreeMap<String, String>, payload_hash: &str) -> String
+{
+    let encoded_key = uri_encode_path(key);
+    let canonical_headers: String = headers.iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let signed_headers_names = get_signed_headers_names(headers);
+
+    let canonical_request: Vec<&str> = vec![method, &encoded_key, query_parameters, &canonical_headers, &signed_headers_names, payload_hash];
     return canonical_request.join("\n");
 }
 
+fn get_canonical_request(options: &GetSignedUrlOptions, query_parameters: &String) -> String
+{
+    let headers = get_canonical_signed_headers(options);
+    return build_canonical_request(&options.method, &options.key, query_parameters, &headers, "UNSIGNED-PAYLOAD");
+}
+
 fn get_signature_payload(options: &GetSignedUrlOptions, payload: String) -> String
 {
     let payload_hash = &sha256(&payload)[..];
@@ -102,7 +179,8 @@ pub fn get_signature_key(options: &GetSignedUrlOptions) -> Vec<u8>
 
 fn get_url(options: &GetSignedUrlOptions, query_parameters: String, signature: String) -> String
 {
-    let url: Vec<&str> = vec!["https://", &options.bucket, ".", &options.endpoint, "/", &options.key, "?", &query_parameters, "&X-Amz-Signature=", &signature];
+    let key = uri_encode_path(&options.key);
+    let url: Vec<&str> = vec!["https://", &options.bucket, ".", &options.endpoint, &key, "?", &query_parameters, "&X-Amz-Signature=", &signature];
     return url.join("");
 }
 
@@ -113,7 +191,7 @@ pub fn get_signed_url(options: &GetSignedUrlOptions) -> String
         None => get_signature_key(&options),
     };
 
-    let query_parameters = get_query_parameters(&options);
+    let query_parameters = get_canonical_query_string(&options);
     let canonical_request = get_canonical_request(&options, &query_parameters);
     let signature_payload = get_signature_payload(&options, canonical_request);
     let signature = hmac_sha_256_hex(&signature_key, &signature_payload);
@@ -121,9 +199,373 @@ pub fn get_signed_url(options: &GetSignedUrlOptions) -> String
     return url;
 }
 
+#[derive(Debug)]
+pub struct AuthorizationHeader {
+    pub authorization: String,
+    pub x_amz_date: String,
+    pub x_amz_content_sha256: String,
+}
+
+pub fn get_authorization_header(options: &GetSignedUrlOptions, payload: &[u8], query_parameters: &BTreeMap<String, String>) -> AuthorizationHeader
+{
+    let signature_key = match options.pre_signature.clone() {
+        Some(pre_signature) => pre_signature,
+        None => get_signature_key(&options),
+    };
+
+    let date = options.date.format("%Y%m%dT%H%M%SZ").to_string();
+    let content_sha256 = sha256_bytes(payload);
+
+    let mut headers = get_canonical_signed_headers(options);
+    headers.insert("x-amz-date".to_string(), date.clone());
+    headers.insert("x-amz-content-sha256".to_string(), content_sha256.clone());
+
+    let canonical_query_string = build_canonical_query_string(query_parameters);
+    let canonical_request = build_canonical_request(&options.method, &options.key, &canonical_query_string, &headers, &content_sha256);
+    let signature_payload = get_signature_payload(&options, canonical_request);
+    let signature = hmac_sha_256_hex(&signature_key, &signature_payload);
+
+    let credential = options.access_key_id.to_string() + "/" + &options.date.format("%Y%m%d").to_string() + "/" + &options.region + "/s3/aws4_request";
+    let signed_headers_names = get_signed_headers_names(&headers);
+
+    let authorization = format!("AWS4-HMAC-SHA256 Credential={}, SignedHeaders={}, Signature={}", credential, signed_headers_names, signature);
+
+    return AuthorizationHeader {
+        authorization,
+        x_amz_date: date,
+        x_amz_content_sha256: content_sha256,
+    };
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool
+{
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+
+    return diff == 0;
+}
+
+fn extract_path(before_query: &str) -> String
+{
+    if let Some(scheme_end) = before_query.find("://") {
+        let after_scheme = &before_query[scheme_end + 3..];
+        return match after_scheme.find('/') {
+            Some(path_start) => after_scheme[path_start..].to_string(),
+            None => "/".to_string(),
+        };
+    }
+
+    return before_query.to_string();
+}
+
+fn parse_query_params(query: &str) -> BTreeMap<String, String>
+{
+    let mut params: BTreeMap<String, String> = BTreeMap::new();
+
+    for pair in query.split('&') {
+        if pair.is_empty() {
+            continue;
+        }
+
+        let mut parts = pair.splitn(2, '=');
+        let key = parts.next().unwrap_or("").to_string();
+        let value = parts.next().unwrap_or("").to_string();
+        params.insert(key, value);
+    }
+
+    return params;
+}
+
+pub fn verify_signed_url(options: &GetSignedUrlOptions, presented_url: &str) -> Result<(), String>
+{
+    let (before_query, query) = presented_url.split_once('?').ok_or("presigned url has no query string")?;
+
+    let mut params = parse_query_params(query);
+
+    let presented_signature = params.remove("X-Amz-Signature").ok_or("missing X-Amz-Signature")?;
+
+    if !params.contains_key("X-Amz-Credential") {
+        return Err("missing X-Amz-Credential".to_string());
+    }
+
+    if !params.contains_key("X-Amz-SignedHeaders") {
+        return Err("missing X-Amz-SignedHeaders".to_string());
+    }
+
+    let date_raw = params.get("X-Amz-Date").ok_or("missing X-Amz-Date")?.clone();
+    let expires_in: i64 = params.get("X-Amz-Expires")
+        .ok_or("missing X-Amz-Expires")?
+        .parse()
+        .map_err(|_| "invalid X-Amz-Expires")?;
+
+    let naive_date = NaiveDateTime::parse_from_str(&date_raw, "%Y%m%dT%H%M%SZ").map_err(|_| "invalid X-Amz-Date")?;
+    let date: DateTime<Utc> = Utc.from_utc_datetime(&naive_date);
+
+    let age = Utc::now().signed_duration_since(date);
+
+    if age > Duration::seconds(expires_in) {
+        return Err("presigned url has expired".to_string());
+    }
+
+    if age > Duration::hours(24) {
+        return Err("presigned url date is too old".to_string());
+    }
+
+    let expected_path = uri_encode_path(&options.key);
+    if extract_path(before_query) != expected_path {
+        return Err("path does not match the signing options".to_string());
+    }
+
+    let mut signing_options = options.clone();
+    signing_options.date = date;
+
+    let query_parameters = params.iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<String>>()
+        .join("&");
+
+    let canonical_request = get_canonical_request(&signing_options, &query_parameters);
+    let signature_payload = get_signature_payload(&signing_options, canonical_request);
+    let signature_key = get_signature_key(&signing_options);
+    let expected_signature = hmac_sha_256_hex(&signature_key, &signature_payload);
+
+    if !constant_time_eq(expected_signature.as_bytes(), presented_signature.as_bytes()) {
+        return Err("signature mismatch".to_string());
+    }
+
+    return Ok(());
+}
+
+#[derive(Debug)]
+pub struct StreamingSigner {
+    signature_key: Vec<u8>,
+    date: String,
+    scope: String,
+    seed_signature: String,
+    previous_signature: String,
+}
+
+impl StreamingSigner {
+    pub fn new(options: &GetSignedUrlOptions, decoded_content_length: u64) -> StreamingSigner
+    {
+        let signature_key = match options.pre_signature.clone() {
+            Some(pre_signature) => pre_signature,
+            None => get_signature_key(options),
+        };
+
+        let date = options.date.format("%Y%m%dT%H%M%SZ").to_string();
+        let scope = options.date.format("%Y%m%d").to_string() + "/" + &options.region + "/s3/aws4_request";
+
+        let mut headers = get_canonical_signed_headers(options);
+        headers.insert("x-amz-date".to_string(), date.clone());
+        headers.insert("x-amz-decoded-content-length".to_string(), decoded_content_length.to_string());
+        headers.insert("content-encoding".to_string(), "aws-chunked".to_string());
+
+        let canonical_request = build_canonical_request(&options.method, &options.key, "", &headers, "STREAMING-AWS4-HMAC-SHA256-PAYLOAD");
+        let signature_payload = get_signature_payload(options, canonical_request);
+        let seed_signature = hmac_sha_256_hex(&signature_key, &signature_payload);
+
+        return StreamingSigner {
+            signature_key,
+            date,
+            scope,
+            seed_signature: seed_signature.clone(),
+            previous_signature: seed_signature,
+        };
+    }
+
+    pub fn seed_signature(&self) -> &str
+    {
+        return &self.seed_signature;
+    }
+
+    pub fn running_signature(&self) -> &str
+    {
+        return &self.previous_signature;
+    }
+
+    pub fn sign_chunk(&mut self, chunk: &[u8]) -> Vec<u8>
+    {
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256-PAYLOAD\n{}\n{}\n{}\n{}\n{}",
+            self.date, self.scope, self.previous_signature, sha256_bytes(b""), sha256_bytes(chunk),
+        );
+
+        let chunk_signature = hmac_sha_256_hex(&self.signature_key, &string_to_sign);
+        self.previous_signature = chunk_signature.clone();
+
+        let mut frame = format!("{:x};chunk-signature={}\r\n", chunk.len(), chunk_signature).into_bytes();
+        frame.extend_from_slice(chunk);
+        frame.extend_from_slice(b"\r\n");
+        return frame;
+    }
+
+    pub fn sign_final_chunk(&mut self) -> Vec<u8>
+    {
+        return self.sign_chunk(&[]);
+    }
+}
+
+#[derive(Debug)]
+pub enum PostPolicyCondition {
+    Exact(String, String),
+    StartsWith(String, String),
+    ContentLengthRange(u64, u64),
+}
+
+#[derive(Debug)]
+pub struct PostPolicyOptions {
+    pub expiration: DateTime<Utc>,
+    pub conditions: Vec<PostPolicyCondition>,
+}
+
+#[derive(Debug)]
+pub struct PresignedPostFields {
+    pub key: String,
+    pub policy: String,
+    pub x_amz_algorithm: String,
+    pub x_amz_credential: String,
+    pub x_amz_date: String,
+    pub x_amz_signature: String,
+    pub x_amz_security_token: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct PresignedPost {
+    pub url: String,
+    pub fields: PresignedPostFields,
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity((data.len() + 2) / 3 * 4);
+
+    for chunk in data.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = *chunk.get(1).unwrap_or(&0);
+        let b2 = *chunk.get(2).unwrap_or(&0);
+
+        out.push(TABLE[(b0 >> 2) as usize] as char);
+        out.push(TABLE[(((b0 & 0x03) << 4) | (b1 >> 4)) as usize] as char);
+        out.push(if chunk.len() > 1 { TABLE[(((b1 & 0x0f) << 2) | (b2 >> 6)) as usize] as char } else { '=' });
+        out.push(if chunk.len() > 2 { TABLE[(b2 & 0x3f) as usize] as char } else { '=' });
+    }
+
+    return out;
+}
+
+fn json_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    return out;
+}
+
+#[cfg(test)]
+fn base64_decode(data: &str) -> Vec<u8> {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let decode_char = |c: u8| -> u8 { TABLE.iter().position(|&t| t == c).unwrap() as u8 };
+
+    let mut out = Vec::with_capacity(data.len() / 4 * 3);
+    for chunk in data.as_bytes().chunks(4) {
+        let c0 = decode_char(chunk[0]);
+        let c1 = decode_char(chunk[1]);
+        out.push((c0 << 2) | (c1 >> 4));
+
+        if chunk[2] != b'=' {
+            let c2 = decode_char(chunk[2]);
+            out.push((c1 << 4) | (c2 >> 2));
+
+            if chunk[3] != b'=' {
+                let c3 = decode_char(chunk[3]);
+                out.push((c2 << 6) | c3);
+            }
+        }
+    }
+
+    return out;
+}
+
+fn condition_to_json(condition: &PostPolicyCondition) -> String {
+    match condition {
+        PostPolicyCondition::Exact(field, value) => format!("{{\"{}\":\"{}\"}}", json_escape(field), json_escape(value)),
+        PostPolicyCondition::StartsWith(field, value) => format!("[\"starts-with\",\"${}\",\"{}\"]", json_escape(field), json_escape(value)),
+        PostPolicyCondition::ContentLengthRange(min, max) => format!("[\"content-length-range\",{},{}]", min, max),
+    }
+}
+
+fn get_policy_document(options: &GetSignedUrlOptions, policy: &PostPolicyOptions, credential: &str, date: &str) -> String {
+    let mut conditions: Vec<String> = vec![
+        condition_to_json(&PostPolicyCondition::Exact("bucket".to_string(), options.bucket.clone())),
+        condition_to_json(&PostPolicyCondition::Exact("key".to_string(), options.key.clone())),
+        condition_to_json(&PostPolicyCondition::Exact("x-amz-algorithm".to_string(), "AWS4-HMAC-SHA256".to_string())),
+        condition_to_json(&PostPolicyCondition::Exact("x-amz-credential".to_string(), credential.to_string())),
+        condition_to_json(&PostPolicyCondition::Exact("x-amz-date".to_string(), date.to_string())),
+    ];
+
+    if let Some(session_token) = &options.session_token {
+        conditions.push(condition_to_json(&PostPolicyCondition::Exact("x-amz-security-token".to_string(), session_token.clone())));
+    }
+
+    for condition in &policy.conditions {
+        conditions.push(condition_to_json(condition));
+    }
+
+    let expiration = policy.expiration.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    return format!("{{\"expiration\":\"{}\",\"conditions\":[{}]}}", expiration, conditions.join(","));
+}
+
+pub fn get_presigned_post(options: &GetSignedUrlOptions, policy: &PostPolicyOptions) -> PresignedPost
+{
+    let signature_key = match options.pre_signature.clone() {
+        Some(pre_signature) => pre_signature,
+        None => get_signature_key(&options),
+    };
+
+    let credential = options.access_key_id.to_string() + "/" + &options.date.format("%Y%m%d").to_string() + "/" + &options.region + "/s3/aws4_request";
+    let date = options.date.format("%Y%m%dT%H%M%SZ").to_string();
+
+    let policy_document = get_policy_document(&options, &policy, &credential, &date);
+    let policy_base64 = base64_encode(policy_document.as_bytes());
+    let signature = hmac_sha_256_hex(&signature_key, &policy_base64);
+
+    let url: Vec<&str> = vec!["https://", &options.bucket, ".", &options.endpoint, "/"];
+
+    return PresignedPost {
+        url: url.join(""),
+        fields: PresignedPostFields {
+            key: options.key.clone(),
+            policy: policy_base64,
+            x_amz_algorithm: "AWS4-HMAC-SHA256".to_string(),
+            x_amz_credential: credential,
+            x_amz_date: date,
+            x_amz_signature: signature,
+            x_amz_security_token: options.session_token.clone(),
+        },
+    };
+}
+
 #[cfg(test)]
 mod test {
     use crate::*;
+    use std::collections::BTreeMap;
 
     #[test]
     fn define_options() {
@@ -181,4 +623,301 @@ mod test {
         let signed_url = get_signed_url(&options);
         println!("Signed url {}", signed_url);
     }
+
+    #[test]
+    fn generate_presigned_post()
+    {
+        let options = GetSignedUrlOptions {
+            key: "uploads/file.mp4".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            ..Default::default()
+        };
+
+        let policy = PostPolicyOptions {
+            expiration: chrono::Utc::now() + chrono::Duration::minutes(5),
+            conditions: vec![
+                PostPolicyCondition::StartsWith("key".to_string(), "uploads/".to_string()),
+                PostPolicyCondition::ContentLengthRange(0, 10_485_760),
+            ],
+        };
+
+        let presigned_post = get_presigned_post(&options, &policy);
+
+        let decoded = base64_decode(&presigned_post.fields.policy);
+        let policy_document = String::from_utf8(decoded).unwrap();
+        assert!(policy_document.contains("\"key\":\"uploads/file.mp4\""));
+        assert!(policy_document.contains("[\"starts-with\",\"$key\",\"uploads/\"]"));
+        assert!(policy_document.contains("[\"content-length-range\",0,10485760]"));
+        assert!(presigned_post.fields.x_amz_security_token.is_none());
+    }
+
+    #[test]
+    fn presigned_post_escapes_quotes_in_condition_values()
+    {
+        let options = GetSignedUrlOptions {
+            key: "uploads/\"evil\":\"1\",\"injected\":\"2".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            ..Default::default()
+        };
+
+        let policy = PostPolicyOptions {
+            expiration: chrono::Utc::now() + chrono::Duration::minutes(5),
+            conditions: vec![],
+        };
+
+        let presigned_post = get_presigned_post(&options, &policy);
+
+        let decoded = base64_decode(&presigned_post.fields.policy);
+        let policy_document = String::from_utf8(decoded).unwrap();
+        assert!(policy_document.contains("\"key\":\"uploads/\\\"evil\\\":\\\"1\\\",\\\"injected\\\":\\\"2\""));
+    }
+
+    #[test]
+    fn presigned_post_includes_session_token()
+    {
+        let options = GetSignedUrlOptions {
+            key: "uploads/file.mp4".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            session_token: Some("token".to_string()),
+            ..Default::default()
+        };
+
+        let policy = PostPolicyOptions {
+            expiration: chrono::Utc::now() + chrono::Duration::minutes(5),
+            conditions: vec![],
+        };
+
+        let presigned_post = get_presigned_post(&options, &policy);
+
+        let decoded = base64_decode(&presigned_post.fields.policy);
+        let policy_document = String::from_utf8(decoded).unwrap();
+        assert!(policy_document.contains("\"x-amz-security-token\":\"token\""));
+        assert_eq!(presigned_post.fields.x_amz_security_token, Some("token".to_string()));
+    }
+
+    #[test]
+    fn generate_signed_url_with_extra_signed_headers()
+    {
+        let mut signed_headers: BTreeMap<String, String> = BTreeMap::new();
+        signed_headers.insert("Content-Type".to_string(), "video/mp4".to_string());
+
+        let options = GetSignedUrlOptions {
+            key: "file.mp4".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            signed_headers,
+            ..Default::default()
+        };
+
+        let signed_url = get_signed_url(&options);
+        assert!(signed_url.contains("X-Amz-SignedHeaders=content-type%3Bhost"));
+    }
+
+    #[test]
+    fn signed_headers_cannot_override_the_real_host()
+    {
+        let mut signed_headers: BTreeMap<String, String> = BTreeMap::new();
+        signed_headers.insert("Host".to_string(), "attacker.example.com".to_string());
+
+        let options = GetSignedUrlOptions {
+            key: "file.mp4".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            signed_headers,
+            ..Default::default()
+        };
+
+        let signed_url = get_signed_url(&options);
+        assert_eq!(verify_signed_url(&options, &signed_url), Ok(()));
+        assert!(signed_url.contains("X-Amz-SignedHeaders=host"));
+        assert!(!signed_url.contains("attacker.example.com"));
+    }
+
+    #[test]
+    fn generate_signed_url_with_session_token()
+    {
+        let options = GetSignedUrlOptions {
+            key: "file.mp4".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            session_token: Some("token".to_string()),
+            ..Default::default()
+        };
+
+        let signed_url = get_signed_url(&options);
+        assert!(signed_url.contains("X-Amz-Security-Token="));
+    }
+
+    #[test]
+    fn generate_signed_url_with_special_characters_in_key()
+    {
+        let options = GetSignedUrlOptions {
+            key: "my folder/file (1).mp4".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            ..Default::default()
+        };
+
+        let signed_url = get_signed_url(&options);
+        assert!(signed_url.contains("/my%20folder/file%20%281%29.mp4?"));
+    }
+
+    #[test]
+    fn generate_authorization_header()
+    {
+        let options = GetSignedUrlOptions {
+            key: "file.mp4".to_string(),
+            method: "PUT".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            ..Default::default()
+        };
+
+        let header = get_authorization_header(&options, b"hello world", &BTreeMap::new());
+        assert!(header.authorization.starts_with("AWS4-HMAC-SHA256 Credential=key/"));
+        assert_eq!(header.x_amz_content_sha256, sha256_bytes(b"hello world"));
+    }
+
+    #[test]
+    fn generate_authorization_header_with_query_parameters()
+    {
+        let options = GetSignedUrlOptions {
+            key: "big-file.bin".to_string(),
+            method: "PUT".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            ..Default::default()
+        };
+
+        let mut query_parameters: BTreeMap<String, String> = BTreeMap::new();
+        query_parameters.insert("partNumber".to_string(), "1".to_string());
+        query_parameters.insert("uploadId".to_string(), "abc123".to_string());
+
+        let with_query = get_authorization_header(&options, b"", &query_parameters);
+        let without_query = get_authorization_header(&options, b"", &BTreeMap::new());
+        assert_ne!(with_query.authorization, without_query.authorization);
+    }
+
+    #[test]
+    fn verify_a_freshly_signed_url()
+    {
+        let options = GetSignedUrlOptions {
+            key: "file.mp4".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            ..Default::default()
+        };
+
+        let signed_url = get_signed_url(&options);
+        assert_eq!(verify_signed_url(&options, &signed_url), Ok(()));
+    }
+
+    #[test]
+    fn reject_a_tampered_signature()
+    {
+        let options = GetSignedUrlOptions {
+            key: "file.mp4".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            ..Default::default()
+        };
+
+        let signed_url = get_signed_url(&options);
+        let tampered_url = signed_url + "0";
+        assert!(verify_signed_url(&options, &tampered_url).is_err());
+    }
+
+    #[test]
+    fn verify_accepts_a_bare_request_target()
+    {
+        let options = GetSignedUrlOptions {
+            key: "file.mp4".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            ..Default::default()
+        };
+
+        let signed_url = get_signed_url(&options);
+        let request_target = signed_url.split_once("cloudflarestorage.com").unwrap().1;
+        assert_eq!(verify_signed_url(&options, request_target), Ok(()));
+    }
+
+    #[test]
+    fn verify_with_independently_constructed_options()
+    {
+        let signing_options = GetSignedUrlOptions {
+            key: "file.mp4".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            date: chrono::Utc::now() - chrono::Duration::seconds(30),
+            ..Default::default()
+        };
+
+        let signed_url = get_signed_url(&signing_options);
+
+        let verifying_options = GetSignedUrlOptions {
+            key: "file.mp4".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            date: chrono::Utc::now(),
+            ..Default::default()
+        };
+
+        assert_eq!(verify_signed_url(&verifying_options, &signed_url), Ok(()));
+    }
+
+    #[test]
+    fn stream_chunks_with_a_streaming_signer()
+    {
+        let options = GetSignedUrlOptions {
+            key: "big-file.bin".to_string(),
+            method: "PUT".to_string(),
+            secret_access_key: "secret".to_string(),
+            access_key_id: "key".to_string(),
+            endpoint: "123.r2.cloudflarestorage.com".to_string(),
+            bucket: "bucket".to_string(),
+            ..Default::default()
+        };
+
+        let mut signer = StreamingSigner::new(&options, 11);
+        let seed_signature = signer.seed_signature().to_string();
+
+        let first_chunk = signer.sign_chunk(b"hello world");
+        assert!(String::from_utf8_lossy(&first_chunk).starts_with(&format!("{:x};chunk-signature=", 11)));
+        assert_ne!(signer.running_signature(), seed_signature);
+
+        let final_chunk = signer.sign_final_chunk();
+        assert!(String::from_utf8_lossy(&final_chunk).starts_with("0;chunk-signature="));
+    }
 }